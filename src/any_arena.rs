@@ -8,6 +8,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use std::alloc::Layout;
 use std::cell::{Cell, RefCell};
 use std::cmp;
 use std::intrinsics;
@@ -16,7 +17,6 @@ use std::mem;
 use std::ptr;
 use std::slice;
 
-use alloc::heap;
 use alloc::raw_vec::RawVec;
 
 /// A slower reflection-based arena that can allocate objects of any type.
@@ -48,10 +48,18 @@ pub struct AnyArena<'longer_than_self> {
     _marker: PhantomData<*mut &'longer_than_self ()>,
 }
 
+/// The initial chunk size used by `AnyArena::new`.
+const PAGE: usize = 4096;
+
+/// The largest size a chunk is allowed to grow to. Growth is geometric up to
+/// this cap, which bounds peak waste to a single cap-sized chunk. The cap is
+/// fixed at 2 MiB, a huge-page boundary, and cannot be configured.
+const HUGE_PAGE: usize = 2 * 1024 * 1024;
+
 impl<'longer_than_self> AnyArena<'longer_than_self> {
-    /// Allocates a new AnyArena with 32 bytes preallocated.
+    /// Allocates a new AnyArena with one page (4096 bytes) preallocated.
     pub fn new() -> AnyArena<'longer_than_self> {
-        AnyArena::new_with_size(32)
+        AnyArena::new_with_size(PAGE)
     }
 
     /// Allocates a new AnyArena with `initial_size` bytes preallocated.
@@ -175,9 +183,17 @@ impl<'longer_than_self> AnyArena<'longer_than_self> {
             // In-place reallocation succeeded.
             false
         } else {
-            // Allocate a new chunk.
-            let new_min_chunk_size = cmp::max(n_bytes, head.capacity());
-            let new_chunk = Chunk::new((new_min_chunk_size + 1).next_power_of_two(), false);
+            // Allocate a new chunk. Grow geometrically from the previous
+            // chunk's size, but clamp to `HUGE_PAGE` so a large arena never
+            // wastes more than one cap-sized chunk. A request larger than the
+            // cap gets its own exactly-sized chunk instead.
+            let new_size = if n_bytes > HUGE_PAGE {
+                round_up(n_bytes, mem::align_of::<*const TyDesc>())
+            } else {
+                cmp::min(head.capacity().saturating_mul(2), HUGE_PAGE)
+            };
+            let new_size = cmp::max(new_size, n_bytes);
+            let new_chunk = Chunk::new(new_size, false);
             let old_chunk = mem::replace(head, new_chunk);
             if old_chunk.fill.get() != 0 {
                 self.chunks.borrow_mut().push(old_chunk);
@@ -202,6 +218,101 @@ impl<'longer_than_self> AnyArena<'longer_than_self> {
         }
     }
 
+    /// Allocates a contiguous slice holding the items produced by `iter` and
+    /// returns a mutable reference to it.
+    ///
+    /// This is more compact than calling `alloc` in a loop: copyable items go
+    /// into the copy chunk with no per-element header, while items with drop
+    /// glue share a single type descriptor for the whole run rather than one
+    /// descriptor per element. The iterator is drained into a temporary buffer
+    /// first, so a panic while iterating never leaves a partially initialized
+    /// run behind.
+    pub fn alloc_from_iter<T, I>(&self, iter: I) -> &mut [T]
+        where T: 'longer_than_self,
+              I: IntoIterator<Item = T>
+    {
+        let mut vec: Vec<T> = iter.into_iter().collect();
+        let len = vec.len();
+        if len == 0 {
+            return &mut [];
+        }
+
+        unsafe {
+            if intrinsics::needs_drop::<T>() {
+                self.alloc_from_iter_noncopy(vec.drain(..), len)
+            } else {
+                self.alloc_from_iter_copy(vec.drain(..), len)
+            }
+        }
+    }
+
+    unsafe fn alloc_from_iter_copy<I>(&self, iter: I, len: usize) -> &mut [I::Item]
+        where I: Iterator
+    {
+        let size = mem::size_of::<I::Item>();
+        let n_bytes = len.checked_mul(size).expect("length overflow");
+        // Check for overflow, matching `alloc_bytes`.
+        self.copy_head.borrow().fill.get().checked_add(n_bytes).expect("length overflow");
+        let ptr = self.alloc_copy_inner(n_bytes, mem::align_of::<I::Item>()) as *mut I::Item;
+        for (i, elem) in iter.enumerate() {
+            ptr::write(ptr.offset(i as isize), elem);
+        }
+        slice::from_raw_parts_mut(ptr, len)
+    }
+
+    unsafe fn alloc_from_iter_noncopy<I>(&self, iter: I, len: usize) -> &mut [I::Item]
+        where I: Iterator
+    {
+        let size = mem::size_of::<I::Item>();
+        let n_bytes = len.checked_mul(size).expect("length overflow");
+        let tydesc = get_tydesc::<I::Item>();
+        let (ty_ptr, ptr) = self.alloc_noncopy_run_inner(n_bytes, mem::align_of::<I::Item>());
+        let ty_ptr = ty_ptr as *mut usize;
+        let ptr = ptr as *mut I::Item;
+        // Write the tydesc and element count, marking the run as *not* yet
+        // initialized so a panic mid-fill doesn't run drop glue on garbage.
+        *ty_ptr = bitpack_run_tydesc_ptr(tydesc, false);
+        *ty_ptr.offset(1) = len;
+        // Move the elements into the arena.
+        for (i, elem) in iter.enumerate() {
+            ptr::write(ptr.offset(i as isize), elem);
+        }
+        // The whole run is initialized; flip the done bit.
+        *ty_ptr = bitpack_run_tydesc_ptr(tydesc, true);
+        slice::from_raw_parts_mut(ptr, len)
+    }
+
+    #[inline]
+    fn alloc_noncopy_run_inner(&self, n_bytes: usize, align: usize) -> (*const u8, *const u8) {
+        let mut head = self.head.borrow_mut();
+        let fill = head.fill.get();
+
+        // The header is the tydesc pointer followed by the element count.
+        let header = mem::size_of::<*const TyDesc>() + mem::size_of::<usize>();
+
+        let mut tydesc_start = fill;
+        let after_header = fill + header;
+        let mut start = round_up(after_header, align);
+        let mut end = round_up(start + n_bytes, mem::align_of::<*const TyDesc>());
+
+        if end > head.capacity() {
+            if self.alloc_grow(&mut *head, tydesc_start, end - tydesc_start) {
+                // Continuing with a newly allocated chunk
+                tydesc_start = 0;
+                start = round_up(header, align);
+                end = round_up(start + n_bytes, mem::align_of::<*const TyDesc>());
+            }
+        }
+
+        head.fill.set(end);
+
+        unsafe {
+            let buf = head.as_ptr();
+            (buf.offset(tydesc_start as isize),
+             buf.offset(start as isize))
+        }
+    }
+
     /// Clears the arena. Deallocates all but the longest chunk which may be reused.
     pub fn clear(&mut self) {
         unsafe {
@@ -218,7 +329,15 @@ impl<'longer_than_self> AnyArena<'longer_than_self> {
     }
 }
 
-impl<'longer_than_self> Drop for AnyArena<'longer_than_self> {
+// The `#[may_dangle]` (dropck eyepatch) attribute tells the borrow checker
+// that dropping an `AnyArena` does not *access* the `'longer_than_self` data
+// beyond running each object's own drop glue. This is sound because
+// `Chunk::destroy` invokes every object's `drop_glue` exactly once and never
+// reads the already-dropped fields, so it is fine for those objects to hold
+// references to sibling objects in the same arena (e.g. cyclic or
+// self-referential graphs). The invariant user destructors must uphold is that
+// they never resurrect or read other arena objects — only their own fields.
+unsafe impl<#[may_dangle] 'longer_than_self> Drop for AnyArena<'longer_than_self> {
     fn drop(&mut self) {
         unsafe {
             self.head.borrow().destroy();
@@ -265,19 +384,36 @@ impl Chunk {
 
         while idx < fill {
             let tydesc_data = buf.offset(idx as isize) as *const usize;
-            let (tydesc, is_done) = un_bitpack_tydesc_ptr(*tydesc_data);
+            let (tydesc, is_done, is_run) = un_bitpack_tydesc_ptr(*tydesc_data);
             let (size, align) = ((*tydesc).size, (*tydesc).align);
 
             let after_tydesc = idx + mem::size_of::<*const TyDesc>();
 
-            let start = round_up(after_tydesc, align);
+            if is_run {
+                // A run stores its element count right after the tydesc, then
+                // `count` elements laid out contiguously with stride `size`.
+                let count = *(buf.offset(after_tydesc as isize) as *const usize);
+                let start = round_up(after_tydesc + mem::size_of::<usize>(), align);
+
+                if is_done {
+                    for i in 0..count {
+                        let elem = start + i * size;
+                        ((*tydesc).drop_glue)(buf.offset(elem as isize) as *mut u8);
+                    }
+                }
 
-            if is_done {
-                ((*tydesc).drop_glue)(buf.offset(start as isize) as *const i8);
-            }
+                // Find where the next tydesc lives
+                idx = round_up(start + size * count, mem::align_of::<*const TyDesc>());
+            } else {
+                let start = round_up(after_tydesc, align);
+
+                if is_done {
+                    ((*tydesc).drop_glue)(buf.offset(start as isize) as *mut u8);
+                }
 
-            // Find where the next tydesc lives
-            idx = round_up(start + size, mem::align_of::<*const TyDesc>());
+                // Find where the next tydesc lives
+                idx = round_up(start + size, mem::align_of::<*const TyDesc>());
+            }
         }
     }
 }
@@ -287,43 +423,63 @@ fn round_up(base: usize, align: usize) -> usize {
     (base.checked_add(align - 1)).unwrap() & !(align - 1)
 }
 
-// HACK(eddyb) TyDesc replacement using a trait object vtable.
-// This could be replaced in the future with a custom DST layout,
-// or `&'static (drop_glue, size, align)` created by a `const fn`.
-// Requirements:
-// * rvalue promotion (issue #1056)
-// * mem::{size_of, align_of} must be const fns
+// A minimal type descriptor: everything the arena needs to lay out an object
+// and run its destructor, without any reflection. The descriptor for each `T`
+// is a `const` item, so taking a reference to it yields a `&'static TyDesc`
+// via rvalue promotion and the allocation fast path pays no runtime cost.
 struct TyDesc {
-    drop_glue: fn(*const i8),
+    drop_glue: unsafe fn(*mut u8),
     size: usize,
     align: usize,
 }
 
-unsafe fn get_tydesc<T>() -> *const TyDesc {
-    use std::raw::TraitObject;
+impl TyDesc {
+    const fn new<T>() -> TyDesc {
+        let layout = Layout::new::<T>();
+        TyDesc {
+            drop_glue: drop_glue::<T>,
+            size: layout.size(),
+            align: layout.align(),
+        }
+    }
+}
 
-    let ptr = &*(heap::EMPTY as *const T);
+// The destructor for `T`, type-erased to the descriptor's signature.
+unsafe fn drop_glue<T>(ptr: *mut u8) {
+    ptr::drop_in_place(ptr as *mut T)
+}
 
-    // Can use any trait that is implemented for all types.
-    let obj = mem::transmute::<&AllTypes, TraitObject>(ptr);
-    obj.vtable as *const TyDesc
+fn get_tydesc<T>() -> *const TyDesc {
+    // The descriptor lives in a per-`T` associated `const`; `&` promotes it to
+    // a `'static` reference so there is no allocation or vtable transmute.
+    struct TyDescHolder<T>(PhantomData<T>);
+    impl<T> TyDescHolder<T> {
+        const TY_DESC: TyDesc = TyDesc::new::<T>();
+    }
+    &TyDescHolder::<T>::TY_DESC
 }
 
-// We encode whether the object a tydesc describes has been
-// initialized in the arena in the low bit of the tydesc pointer. This
-// is necessary in order to properly do cleanup if a panic occurs
-// during an initializer.
+// We encode a little metadata in the low bits of the tydesc pointer, which is
+// always at least 4-byte aligned. Bit 0 records whether the object the tydesc
+// describes has been initialized in the arena; this is necessary in order to
+// properly do cleanup if a panic occurs during an initializer. Bit 1 records
+// whether the header describes a contiguous *run* of elements (allocated by
+// `alloc_from_iter`) rather than a single object; a run stores its element
+// count as a `usize` immediately after the tydesc pointer.
+const DONE_BIT: usize = 1;
+const RUN_BIT: usize = 2;
+
 #[inline]
 fn bitpack_tydesc_ptr(p: *const TyDesc, is_done: bool) -> usize {
     p as usize | (is_done as usize)
 }
 #[inline]
-fn un_bitpack_tydesc_ptr(p: usize) -> (*const TyDesc, bool) {
-    ((p & !1) as *const TyDesc, p & 1 == 1)
+fn bitpack_run_tydesc_ptr(p: *const TyDesc, is_done: bool) -> usize {
+    p as usize | RUN_BIT | (is_done as usize)
 }
-
-trait AllTypes {
-    fn dummy(&self) {}
+#[inline]
+fn un_bitpack_tydesc_ptr(p: usize) -> (*const TyDesc, bool, bool) {
+    ((p & !(DONE_BIT | RUN_BIT)) as *const TyDesc,
+     p & DONE_BIT == DONE_BIT,
+     p & RUN_BIT == RUN_BIT)
 }
-
-impl<T: ?Sized> AllTypes for T {}
@@ -0,0 +1,154 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::cell::{Cell, RefCell};
+use std::cmp;
+use std::intrinsics;
+use std::mem;
+use std::ptr;
+use std::slice;
+use std::str;
+
+use typed_arena::TypedArenaChunk;
+
+const PAGE: usize = 4096;
+
+/// A leaner companion to `AnyArena`'s copy half: a byte-bump allocator that
+/// only accepts types without drop glue. Because nothing is ever dropped there
+/// is no per-object header, so values of different types can share a chunk
+/// densely. This is handy for interning strings and small plain-old-data.
+pub struct DroplessArena {
+    /// A pointer to the next byte to be allocated.
+    ptr: Cell<*mut u8>,
+
+    /// A pointer to the end of the current chunk.
+    end: Cell<*mut u8>,
+
+    /// A vector of arena chunks.
+    chunks: RefCell<Vec<TypedArenaChunk<u8>>>,
+}
+
+impl DroplessArena {
+    /// Creates a new, empty `DroplessArena`.
+    pub fn new() -> DroplessArena {
+        DroplessArena {
+            // The first allocation will trigger a grow(), as both pointers are
+            // null.
+            ptr: Cell::new(0 as *mut u8),
+            end: Cell::new(0 as *mut u8),
+            chunks: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Grows the arena so that it can fit at least `needed_bytes` more.
+    #[inline(never)]
+    #[cold]
+    fn grow(&self, needed_bytes: usize) {
+        unsafe {
+            let mut chunks = self.chunks.borrow_mut();
+            let (chunk, mut new_capacity);
+            if let Some(last_chunk) = chunks.last_mut() {
+                let used_bytes = self.ptr.get() as usize - last_chunk.start() as usize;
+                if last_chunk.storage.reserve_in_place(used_bytes, needed_bytes) {
+                    self.end.set(last_chunk.end());
+                    return;
+                } else {
+                    new_capacity = last_chunk.storage.cap();
+                    loop {
+                        new_capacity = new_capacity.checked_mul(2).unwrap();
+                        if new_capacity >= used_bytes + needed_bytes {
+                            break;
+                        }
+                    }
+                }
+            } else {
+                new_capacity = cmp::max(needed_bytes, PAGE);
+            }
+            chunk = TypedArenaChunk::<u8>::new(new_capacity);
+            self.ptr.set(chunk.start());
+            self.end.set(chunk.end());
+            chunks.push(chunk);
+        }
+    }
+
+    /// Allocates `bytes` bytes aligned to `align` and returns a pointer to the
+    /// start of the run.
+    #[inline]
+    fn alloc_raw(&self, bytes: usize, align: usize) -> *mut u8 {
+        unsafe {
+            assert!(bytes != 0);
+
+            // Round the bump pointer up to the requested alignment.
+            let mut start = round_up(self.ptr.get() as usize, align);
+            if start + bytes > self.end.get() as usize {
+                // The request doesn't fit; ask for enough to cover the
+                // alignment padding as well.
+                self.grow(bytes + align);
+                start = round_up(self.ptr.get() as usize, align);
+            }
+
+            self.ptr.set((start + bytes) as *mut u8);
+            start as *mut u8
+        }
+    }
+
+    /// Allocates a single object in the arena and returns a reference to it.
+    #[inline]
+    pub fn alloc<T>(&self, object: T) -> &mut T {
+        unsafe {
+            assert!(!intrinsics::needs_drop::<T>());
+
+            let ptr = self.alloc_raw(mem::size_of::<T>(), mem::align_of::<T>()) as *mut T;
+            ptr::write(ptr, object);
+            &mut *ptr
+        }
+    }
+
+    /// Allocates a contiguous copy of `slice` in the arena and returns a
+    /// reference to it.
+    #[inline]
+    pub fn alloc_slice<T>(&self, slice: &[T]) -> &mut [T]
+        where T: Copy
+    {
+        assert!(!intrinsics::needs_drop::<T>());
+        assert!(mem::size_of::<T>() != 0);
+
+        // Interning an empty slice is valid and must not hit `alloc_raw`'s
+        // `bytes != 0` assertion; hand back a zero-length slice directly.
+        if slice.is_empty() {
+            return &mut [];
+        }
+
+        let ptr = self.alloc_raw(slice.len() * mem::size_of::<T>(), mem::align_of::<T>()) as *mut T;
+
+        unsafe {
+            let arena_slice = slice::from_raw_parts_mut(ptr, slice.len());
+            arena_slice.copy_from_slice(slice);
+            arena_slice
+        }
+    }
+
+    /// Allocates a copy of `string` in the arena and returns a reference to it.
+    #[inline]
+    pub fn alloc_str(&self, string: &str) -> &mut str {
+        if string.is_empty() {
+            return unsafe { str::from_utf8_unchecked_mut(&mut []) };
+        }
+
+        let slice = self.alloc_slice(string.as_bytes());
+
+        unsafe { str::from_utf8_unchecked_mut(slice) }
+    }
+}
+
+#[inline]
+fn round_up(base: usize, align: usize) -> usize {
+    (base.checked_add(align - 1)).unwrap() & !(align - 1)
+}
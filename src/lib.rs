@@ -0,0 +1,31 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The arena, a fast but limited type of allocator.
+//!
+//! Arenas are a type of allocator that destroy the objects within, all at
+//! once, once the arena itself is destroyed. They do not support deallocation
+//! of individual objects while the arena itself is still alive. The benefit
+//! of an arena is very fast allocation; just a pointer bump.
+
+#![feature(alloc)]
+#![feature(core_intrinsics)]
+#![feature(heap_api)]
+#![feature(dropck_eyepatch)]
+
+extern crate alloc;
+
+pub use any_arena::AnyArena;
+pub use dropless_arena::DroplessArena;
+pub use typed_arena::TypedArena;
+
+mod any_arena;
+mod dropless_arena;
+mod typed_arena;
@@ -0,0 +1,209 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::cell::{Cell, RefCell};
+use std::cmp;
+use std::intrinsics;
+use std::marker::PhantomData;
+use std::mem;
+use std::ptr;
+
+use alloc::heap;
+use alloc::raw_vec::RawVec;
+
+/// A fast arena that can only hold objects of a single type.
+///
+/// Unlike `AnyArena`, this arena knows the type of its contents statically, so
+/// it stores neither a per-object type descriptor nor any init-tracking bits.
+/// Allocation is a single pointer bump within the current chunk, and on drop
+/// the arena walks the filled prefix of each chunk and runs `drop_in_place`
+/// over it. When `T` has no drop glue the walk is skipped entirely, and
+/// zero-sized `T` is handled by treating `ptr`/`end` as a plain counter.
+pub struct TypedArena<T> {
+    /// A pointer to the next object to be allocated.
+    ptr: Cell<*mut T>,
+
+    /// A pointer to the end of the allocated area. When this pointer is
+    /// reached, a new chunk is allocated.
+    end: Cell<*mut T>,
+
+    /// A vector of arena chunks.
+    chunks: RefCell<Vec<TypedArenaChunk<T>>>,
+
+    /// Marker indicating that dropping the arena causes its owned
+    /// instances of `T` to be dropped.
+    _own: PhantomData<T>,
+}
+
+pub struct TypedArenaChunk<T> {
+    /// The raw storage for the arena chunk.
+    pub storage: RawVec<T>,
+}
+
+impl<T> TypedArenaChunk<T> {
+    #[inline]
+    pub unsafe fn new(capacity: usize) -> TypedArenaChunk<T> {
+        TypedArenaChunk {
+            storage: RawVec::with_capacity(capacity),
+        }
+    }
+
+    /// Destroys this arena chunk.
+    #[inline]
+    unsafe fn destroy(&mut self, len: usize) {
+        // The branch on needs_drop::<T>() is an -O1 optimization.
+        // The compiler can't make this optimization for us because it doesn't
+        // know the uninitialized part of the chunk is never read.
+        if intrinsics::needs_drop::<T>() {
+            let mut start = self.start();
+            // Destroy all allocated objects.
+            for _ in 0..len {
+                ptr::drop_in_place(start);
+                start = start.offset(1);
+            }
+        }
+    }
+
+    // Returns a pointer to the first allocated object.
+    #[inline]
+    pub fn start(&self) -> *mut T {
+        self.storage.ptr()
+    }
+
+    // Returns a pointer to the end of the allocated space.
+    #[inline]
+    pub fn end(&self) -> *mut T {
+        unsafe {
+            if mem::size_of::<T>() == 0 {
+                // A pointer as large as possible for zero-sized elements.
+                !0 as *mut T
+            } else {
+                self.start().offset(self.storage.cap() as isize)
+            }
+        }
+    }
+}
+
+const PAGE: usize = 4096;
+
+impl<T> TypedArena<T> {
+    /// Creates a new `TypedArena`.
+    #[inline]
+    pub fn new() -> TypedArena<T> {
+        TypedArena {
+            // We set both `ptr` and `end` to 0 so that the first call to
+            // alloc() will trigger a grow().
+            ptr: Cell::new(0 as *mut T),
+            end: Cell::new(0 as *mut T),
+            chunks: RefCell::new(Vec::new()),
+            _own: PhantomData,
+        }
+    }
+
+    /// Allocates an object in the `TypedArena`, returning a reference to it.
+    #[inline]
+    pub fn alloc(&self, object: T) -> &mut T {
+        if self.ptr == self.end {
+            self.grow(1)
+        }
+
+        unsafe {
+            if mem::size_of::<T>() == 0 {
+                self.ptr.set(intrinsics::arith_offset(self.ptr.get() as *mut u8, 1) as *mut T);
+                let ptr = heap::EMPTY as *mut T;
+                // Don't drop the object. This `mem::forget` is handled by the
+                // no-op write below, since the object is zero-sized.
+                ptr::write(ptr, object);
+                &mut *ptr
+            } else {
+                let ptr = self.ptr.get();
+                // Advance the pointer.
+                self.ptr.set(self.ptr.get().offset(1));
+                // Write into uninitialized memory.
+                ptr::write(ptr, object);
+                &mut *ptr
+            }
+        }
+    }
+
+    /// Grows the arena.
+    #[inline(never)]
+    #[cold]
+    fn grow(&self, n: usize) {
+        unsafe {
+            let mut chunks = self.chunks.borrow_mut();
+            let (chunk, mut new_capacity);
+            if let Some(last_chunk) = chunks.last_mut() {
+                let used_bytes = self.ptr.get() as usize - last_chunk.start() as usize;
+                let currently_used_cap = used_bytes / mem::size_of::<T>();
+                if last_chunk.storage.reserve_in_place(currently_used_cap, n) {
+                    self.end.set(last_chunk.end());
+                    return;
+                } else {
+                    new_capacity = last_chunk.storage.cap();
+                    loop {
+                        new_capacity = new_capacity.checked_mul(2).unwrap();
+                        if new_capacity >= currently_used_cap + n {
+                            break;
+                        }
+                    }
+                }
+            } else {
+                let elem_size = cmp::max(1, mem::size_of::<T>());
+                new_capacity = cmp::max(n, PAGE / elem_size);
+            }
+            chunk = TypedArenaChunk::<T>::new(new_capacity);
+            self.ptr.set(chunk.start());
+            self.end.set(chunk.end());
+            chunks.push(chunk);
+        }
+    }
+
+    /// Clears the last chunk's filled prefix and resets the allocation
+    /// pointer to its start.
+    unsafe fn clear_last_chunk(&self, last_chunk: &mut TypedArenaChunk<T>) {
+        // Determine how much was filled.
+        let start = last_chunk.start() as usize;
+        // We obtain the value of the pointer to the first uninitialized element.
+        let end = self.ptr.get() as usize;
+        // We then calculate the number of elements to be dropped in the last chunk,
+        // which is the filled area's length.
+        let diff = if mem::size_of::<T>() == 0 {
+            // `T` is ZST. It can't have a drop flag, so the value here doesn't
+            // matter. We still need to free the storage, though.
+            end - start
+        } else {
+            (end - start) / mem::size_of::<T>()
+        };
+        // Pass that to the `destroy` method.
+        last_chunk.destroy(diff);
+        // Reset the chunk.
+        self.ptr.set(last_chunk.start());
+    }
+}
+
+unsafe impl<#[may_dangle] T> Drop for TypedArena<T> {
+    fn drop(&mut self) {
+        unsafe {
+            // Determine how much was filled.
+            let mut chunks_borrow = self.chunks.borrow_mut();
+            if let Some(mut last_chunk) = chunks_borrow.pop() {
+                // Drop the contents of the last chunk.
+                self.clear_last_chunk(&mut last_chunk);
+                // The last chunk will be dropped. Destroy all other chunks.
+                for chunk in chunks_borrow.iter_mut() {
+                    let cap = chunk.storage.cap();
+                    chunk.destroy(cap);
+                }
+            }
+            // RawVec handles deallocation of `last_chunk` and `self.chunks`.
+        }
+    }
+}
@@ -14,10 +14,13 @@ extern crate any_arena;
 extern crate test;
 
 use std::cell::Cell;
+use std::mem;
 use std::rc::Rc;
 use self::test::Bencher;
 
 use any_arena::AnyArena;
+use any_arena::DroplessArena;
+use any_arena::TypedArena;
 
 #[allow(dead_code)]
 #[derive(Debug, Eq, PartialEq)]
@@ -83,6 +86,33 @@ pub fn test_arena_alloc_bytes() {
     }
 }
 
+#[test]
+fn test_arena_alloc_from_iter() {
+    let arena = AnyArena::new();
+    // Copyable elements go into the copy chunk with no per-element header.
+    let nums: &mut [i32] = arena.alloc_from_iter(0..10);
+    assert_eq!(nums, &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    // An empty iterator yields an empty slice without touching the arena.
+    let empty: &mut [i32] = arena.alloc_from_iter(0..0);
+    assert_eq!(empty.len(), 0);
+    // Elements with drop glue share a single tydesc for the whole run.
+    let strings: &mut [String] = arena.alloc_from_iter((0..5).map(|i| i.to_string()));
+    assert_eq!(strings, &["0", "1", "2", "3", "4"]);
+}
+
+#[test]
+fn test_arena_alloc_from_iter_drop_count() {
+    let counter = Cell::new(0);
+    {
+        let arena = AnyArena::new();
+        for _ in 0..10 {
+            arena.alloc_from_iter((0..10).map(|_| DropCounter { count: &counter }));
+        }
+        // dropping
+    };
+    assert_eq!(counter.get(), 100);
+}
+
 #[test]
 fn test_arena_destructors() {
     let arena = AnyArena::new();
@@ -192,6 +222,119 @@ fn test_arena_drop_small_count() {
     assert_eq!(DROP_COUNTER.with(|c| c.get()), 100);
 }
 
+// TypedArena tests
+
+#[test]
+fn test_typed_arena_alloc() {
+    let arena = TypedArena::new();
+    let mut points = vec![];
+    for i in 0..1000 {
+        points.push(arena.alloc(Point { x: i, y: i + 1, z: i + 2 }));
+    }
+    for (i, point) in points.iter().enumerate() {
+        let i = i as i32;
+        assert_eq!(**point, Point { x: i, y: i + 1, z: i + 2 });
+    }
+}
+
+#[test]
+fn test_typed_arena_drop_count() {
+    let counter = Cell::new(0);
+    {
+        let arena = TypedArena::new();
+        for _ in 0..100 {
+            // Allocate something with drop glue to make sure it doesn't leak.
+            arena.alloc(DropCounter { count: &counter });
+        }
+        // dropping
+    };
+    assert_eq!(counter.get(), 100);
+}
+
+#[test]
+fn test_typed_arena_zero_sized() {
+    let arena = TypedArena::new();
+    let mut units = vec![];
+    for _ in 0..1000 {
+        units.push(arena.alloc(()));
+    }
+    assert_eq!(units.len(), 1000);
+}
+
+#[test]
+fn test_typed_arena_chunk_growth() {
+    // Allocate far more than a single chunk can hold so the arena is forced
+    // to grow into further chunks, and check every value survives.
+    let arena = TypedArena::new();
+    let mut refs = vec![];
+    for i in 0..100_000 {
+        refs.push(arena.alloc(i));
+    }
+    for (i, r) in refs.iter().enumerate() {
+        assert_eq!(**r, i);
+    }
+}
+
+// DroplessArena tests
+
+#[test]
+fn test_dropless_arena_alloc_mixed() {
+    // Values of different POD types share a single chunk densely.
+    let arena = DroplessArena::new();
+    let a: &mut u8 = arena.alloc(7u8);
+    let b: &mut u32 = arena.alloc(0xdead_beef);
+    let c: &mut char = arena.alloc('z');
+    assert_eq!(*a, 7);
+    assert_eq!(*b, 0xdead_beef);
+    assert_eq!(*c, 'z');
+}
+
+#[test]
+fn test_dropless_arena_alloc_slice() {
+    let arena = DroplessArena::new();
+    let nums = arena.alloc_slice(&[1, 2, 3, 4, 5]);
+    assert_eq!(nums, &[1, 2, 3, 4, 5]);
+    // An empty slice must not trip the non-empty assertion.
+    let empty = arena.alloc_slice::<i32>(&[]);
+    assert_eq!(empty.len(), 0);
+}
+
+#[test]
+fn test_dropless_arena_alloc_str() {
+    let arena = DroplessArena::new();
+    let s = arena.alloc_str("hello world");
+    assert_eq!(s, "hello world");
+    // Interning an empty string is valid.
+    let empty = arena.alloc_str("");
+    assert_eq!(empty, "");
+}
+
+#[test]
+fn test_dropless_arena_alignment() {
+    // Interleave types with different alignments and check each reference is
+    // aligned for its type.
+    let arena = DroplessArena::new();
+    for _ in 0..1000 {
+        let _ = arena.alloc(1u8);
+        let wide: &mut u64 = arena.alloc(0x0102_0304_0506_0708);
+        assert_eq!(*wide, 0x0102_0304_0506_0708);
+        assert_eq!(wide as *mut u64 as usize % mem::align_of::<u64>(), 0);
+    }
+}
+
+#[test]
+fn test_dropless_arena_growth() {
+    // Allocate enough to span many chunks and verify every value round-trips.
+    let arena = DroplessArena::new();
+    let mut refs = vec![];
+    for i in 0..100_000u64 {
+        refs.push(arena.alloc(i));
+    }
+    for (i, r) in refs.iter().enumerate() {
+        assert_eq!(**r, i as u64);
+    }
+}
+
 #[bench]
 pub fn bench_arena_noncopy(b: &mut Bencher) {
     let arena = AnyArena::new();